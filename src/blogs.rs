@@ -15,12 +15,29 @@ pub(crate) struct Manifest {
     /// Title to use in the html header.
     pub(crate) index_title: String,
 
+    /// Absolute base URL of the blog, used to build absolute feed links.
+    pub(crate) link: Option<String>,
+
+    /// How many posts to show per index page. Defaults to all on one page.
+    pub(crate) posts_per_page: Option<usize>,
 }
 
 #[derive(Serialize)]
 pub(crate) struct Blog {
     title: String,
     index_title: String,
+    link: Option<String>,
+    posts_per_page: Option<usize>,
+    posts: Vec<Post>,
+    tags: Vec<Tag>,
+}
+
+/// A single tag together with the posts that carry it.
+#[derive(Serialize)]
+pub(crate) struct Tag {
+    name: String,
+    slug: String,
+    count: usize,
     posts: Vec<Post>,
 }
 
@@ -33,11 +50,22 @@ impl Blog {
         for entry in std::fs::read_dir(dir)? {
             let path = entry?.path();
             let ext = path.extension().and_then(|e| e.to_str());
-            if path.metadata()?.file_type().is_file() && ext == Some(POSTS_EXT) {
+            let file_type = path.metadata()?.file_type();
+            if file_type.is_file() && ext == Some(POSTS_EXT) {
+                posts.push(Post::open(&path)?);
+            } else if file_type.is_dir() && is_post_dir(&path) {
+                // A folder-based post, e.g. `2024-01-02-my-trip/index.md`.
                 posts.push(Post::open(&path)?);
             }
         }
 
+        // Drafts are hidden from every rendered surface (index, feed, tags)
+        // unless explicitly enabled for local preview.
+        let drafts_enabled = std::env::var("BLOG_DRAFTS").map(|v| v == "1").unwrap_or(false);
+        if !drafts_enabled {
+            posts.retain(|post| !post.draft);
+        }
+
         posts.sort_by_key(|post| {
             format!(
                 "{}-{:02}-{:02}-{}",
@@ -47,25 +75,22 @@ impl Blog {
         posts.reverse();
 
         // Decide which posts should show the year in the index.
-        posts[0].show_year = true;
-        for i in 1..posts.len() {
-            posts[i].show_year = posts[i - 1].year != posts[i].year;
-        }
-
-        // Make the updated time is unique, by incrementing seconds for duplicates
-        let mut last_matching_updated = 0;
-        for i in 1..posts.len() {
-            if posts[i].updated == posts[last_matching_updated].updated {
-                posts[i].set_updated((i - last_matching_updated) as u32);
-            } else {
-                last_matching_updated = i;
+        if !posts.is_empty() {
+            posts[0].show_year = true;
+            for i in 1..posts.len() {
+                posts[i].show_year = posts[i - 1].year != posts[i].year;
             }
         }
 
+        let tags = build_tags(&posts);
+
         Ok(Blog {
             title: manifest.title,
             index_title: manifest.index_title,
+            link: manifest.link,
+            posts_per_page: manifest.posts_per_page,
             posts,
+            tags,
         })
     }
 
@@ -77,6 +102,11 @@ impl Blog {
         &self.index_title
     }
 
+    /// The absolute base URL of the blog, if one was configured in the manifest.
+    pub(crate) fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
     pub(crate) fn path_back_to_root(&self) -> PathBuf {
         PathBuf::new().components().map(|_| Path::new("../")).collect()
     }
@@ -84,4 +114,91 @@ impl Blog {
     pub(crate) fn posts(&self) -> &[Post] {
         &self.posts
     }
+
+    /// Page size for the index, falling back to a single page of every post.
+    pub(crate) fn posts_per_page(&self) -> usize {
+        self.posts_per_page.unwrap_or(self.posts.len().max(1))
+    }
+
+    /// Distinct post years, newest first, preserving the reverse-chronological
+    /// order of `posts`.
+    pub(crate) fn years(&self) -> Vec<i32> {
+        let mut years = Vec::new();
+        for post in &self.posts {
+            if years.last() != Some(&post.year) {
+                years.push(post.year);
+            }
+        }
+        years
+    }
+
+    pub(crate) fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+}
+
+impl Tag {
+    pub(crate) fn slug(&self) -> &str {
+        &self.slug
+    }
+}
+
+/// Build the inverted index mapping each tag to the posts that carry it. Posts
+/// keep the reverse-chronological order they already have in `posts`, and tags
+/// are sorted alphabetically by slug so the overview is stable across rebuilds.
+fn build_tags(posts: &[Post]) -> Vec<Tag> {
+    let mut by_slug: std::collections::BTreeMap<String, Tag> = std::collections::BTreeMap::new();
+    for post in posts {
+        for name in &post.tags {
+            let slug = slugify(name);
+            let tag = by_slug.entry(slug.clone()).or_insert_with(|| Tag {
+                name: name.clone(),
+                slug,
+                count: 0,
+                posts: Vec::new(),
+            });
+            tag.posts.push(post.clone());
+            tag.count += 1;
+        }
+    }
+    by_slug.into_values().collect()
+}
+
+/// Whether a directory is a folder-based post: its name follows the
+/// `YYYY-MM-DD-slug` convention and it contains an `index.md`.
+fn is_post_dir(path: &Path) -> bool {
+    if !path.join("index.md").is_file() {
+        return false;
+    }
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let mut parts = name.splitn(4, '-');
+    let is_numeric = |part: Option<&str>, len: usize| {
+        matches!(part, Some(p) if p.len() == len && p.bytes().all(|b| b.is_ascii_digit()))
+    };
+    is_numeric(parts.next(), 4)
+        && is_numeric(parts.next(), 2)
+        && is_numeric(parts.next(), 2)
+        && parts.next().is_some()
+}
+
+/// Normalize a tag into a URL-safe slug: lowercase, with runs of spaces and
+/// punctuation collapsed into single hyphens and leading/trailing hyphens
+/// trimmed.
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    let mut last_was_hyphen = true;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.extend(c.to_lowercase());
+            last_was_hyphen = false;
+        } else if !last_was_hyphen {
+            slug.push('-');
+            last_was_hyphen = true;
+        }
+    }
+    slug.truncate(slug.trim_end_matches('-').len());
+    slug
 }
\ No newline at end of file
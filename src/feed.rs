@@ -0,0 +1,86 @@
+use super::blogs::Blog;
+use sha2::{Digest, Sha256};
+
+/// Render the whole `blog` as an [Atom 1.0] feed document.
+///
+/// The top-level `<updated>` is taken from the most recent post's `updated`
+/// timestamp, and each entry is given a stable `<id>` derived from a hash of
+/// its canonical URL so it stays constant across rebuilds even if the title
+/// changes. When the manifest carries a `link`, each post's canonical URL (the
+/// entry's `<link href>` and the input to its `<id>` hash) is made absolute
+/// against it.
+///
+/// [Atom 1.0]: https://datatracker.ietf.org/doc/html/rfc4287
+pub(crate) fn render_atom(blog: &Blog) -> String {
+    let base = blog.link().map(|link| link.trim_end_matches('/'));
+
+    let absolute = |url: &str| match base {
+        Some(base) => format!("{}/{}", base, url),
+        None => url.to_string(),
+    };
+
+    let feed_updated = blog
+        .posts()
+        .first()
+        .map(|post| post.updated.clone())
+        .unwrap_or_default();
+
+    let mut atom = String::new();
+    atom.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    atom.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    atom.push_str(&format!("  <title>{}</title>\n", escape(blog.title())));
+    atom.push_str(&format!("  <id>{}</id>\n", absolute("feed.xml")));
+    if let Some(base) = base {
+        atom.push_str(&format!(
+            "  <link rel=\"self\" href=\"{}/feed.xml\"/>\n",
+            base
+        ));
+        atom.push_str(&format!("  <link href=\"{}/\"/>\n", base));
+    }
+    atom.push_str(&format!("  <updated>{}</updated>\n", escape(&feed_updated)));
+
+    for post in blog.posts() {
+        let canonical = absolute(&post.url);
+        atom.push_str("  <entry>\n");
+        atom.push_str(&format!("    <title>{}</title>\n", escape(&post.title)));
+        atom.push_str(&format!("    <id>urn:sha256:{}</id>\n", guid(&canonical)));
+        atom.push_str(&format!("    <link href=\"{}\"/>\n", escape(&canonical)));
+        atom.push_str(&format!("    <author><name>{}</name></author>\n", escape(&post.author)));
+        atom.push_str(&format!("    <published>{}</published>\n", escape(&post.published)));
+        atom.push_str(&format!("    <updated>{}</updated>\n", escape(&post.updated)));
+        atom.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n",
+            escape(&post.contents)
+        ));
+        atom.push_str("  </entry>\n");
+    }
+
+    atom.push_str("</feed>\n");
+    atom
+}
+
+/// A stable GUID for a post, a hex SHA-256 digest of its canonical URL.
+fn guid(canonical_url: &str) -> String {
+    let digest = Sha256::digest(canonical_url.as_bytes());
+    let mut hex = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+/// Escape the five XML predefined entities so arbitrary text is safe to embed.
+fn escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
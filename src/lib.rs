@@ -1,11 +1,11 @@
 mod blogs;
+mod feed;
 mod posts;
 
 use self::blogs::Blog;
 use self::posts::Post;
 use handlebars::{handlebars_helper, Handlebars};
 use sass_rs::{compile_file, Options};
-use serde_derive::Serialize;
 use serde_json::json;
 use std::convert::AsRef;
 use std::error::Error;
@@ -16,20 +16,49 @@ use std::path::{Path, PathBuf};
 struct Generator<'a> {
     handlebars: Handlebars<'a>,
     blog: Blog,
+    locations: Locations,
     out_directory: PathBuf,
 }
 
-#[derive(Debug, Serialize)]
-struct Releases {
-    releases: Vec<ReleasePost>,
-    feed_updated: String,
+/// Filesystem layout for a single blog. Every path the generator reads from or
+/// writes to is derived from these, so one repo can host several blogs.
+pub(crate) struct Locations {
+    pub(crate) out_dir: PathBuf,
+    pub(crate) posts_dir: PathBuf,
+    pub(crate) templates_dir: PathBuf,
+    pub(crate) static_dir: PathBuf,
+    pub(crate) styles_dir: PathBuf,
 }
 
-#[derive(Debug, Serialize)]
-struct ReleasePost {
-    title: String,
-    url: String,
+impl Default for Locations {
+    fn default() -> Self {
+        Locations {
+            out_dir: "site".into(),
+            posts_dir: "posts".into(),
+            templates_dir: "templates".into(),
+            static_dir: "static".into(),
+            styles_dir: "src/styles".into(),
+        }
+    }
 }
+
+impl Locations {
+    /// Apply `BLOG_*_DIR` environment overrides on top of the current values.
+    fn with_env_overrides(mut self) -> Self {
+        let set = |slot: &mut PathBuf, var: &str| {
+            if let Ok(value) = std::env::var(var) {
+                *slot = value.into();
+            }
+        };
+        set(&mut self.out_dir, "BLOG_OUT_DIR");
+        set(&mut self.posts_dir, "BLOG_POSTS_DIR");
+        set(&mut self.templates_dir, "BLOG_TEMPLATES_DIR");
+        set(&mut self.static_dir, "BLOG_STATIC_DIR");
+        set(&mut self.styles_dir, "BLOG_STYLES_DIR");
+        self
+    }
+}
+
 handlebars_helper!(hb_month_name_helper: |month_num: u64| match month_num {
     1 => "Jan.",
     2 => "Feb.",
@@ -46,20 +75,21 @@ handlebars_helper!(hb_month_name_helper: |month_num: u64| match month_num {
     _ => "Error!",
 });
 
+handlebars_helper!(hb_slugify_helper: |name: str| blogs::slugify(name));
+
 impl<'a> Generator<'a> {
-    fn new(
-        out_directory: impl AsRef<Path>,
-        posts_directory: impl AsRef<Path>,
-    ) -> Result<Self, Box<dyn Error>> {
+    fn new(locations: Locations) -> Result<Self, Box<dyn Error>> {
         let mut handlebars = Handlebars::new();
         handlebars.set_strict_mode(true);
-        handlebars.register_templates_directory(".hbs", "templates")?;
+        handlebars.register_templates_directory(".hbs", &locations.templates_dir)?;
         handlebars.register_helper("month_name", Box::new(hb_month_name_helper));
+        handlebars.register_helper("slugify", Box::new(hb_slugify_helper));
 
         Ok(Generator {
             handlebars,
-            blog: Blog::load(posts_directory.as_ref())?,
-            out_directory: out_directory.as_ref().into(),
+            blog: Blog::load(&locations.posts_dir)?,
+            out_directory: locations.out_dir.clone(),
+            locations,
         })
     }
 
@@ -86,31 +116,43 @@ impl<'a> Generator<'a> {
         self.render_blog(&self.blog)?;
         self.compile_sass("app");
         self.compile_sass("fonts");
+        self.compile_syntax_css();
         self.concat_vendor_css(vec!["skeleton", "tachyons"]);
         self.copy_static_files()?;
         Ok(())
     }
 
+    /// The directory generated/vendored stylesheets are written into.
+    fn css_dir(&self) -> PathBuf {
+        self.locations.static_dir.join("styles")
+    }
+
     fn compile_sass(&self, filename: &str) {
-        let scss_file = format!("./src/styles/{}.scss", filename);
-        let css_file = format!("./static/styles/{}.css", filename);
+        let scss_file = self.locations.styles_dir.join(format!("{}.scss", filename));
+        let css_file = self.css_dir().join(format!("{}.css", filename));
 
         let css = compile_file(&scss_file, Options::default())
-            .expect(&format!("couldn't compile sass: {}", &scss_file));
-        let mut file =
-            File::create(&css_file).expect(&format!("couldn't make css file: {}", &css_file));
+            .expect(&format!("couldn't compile sass: {}", scss_file.display()));
+        let mut file = File::create(&css_file)
+            .expect(&format!("couldn't make css file: {}", css_file.display()));
         file.write_all(&css.into_bytes())
-            .expect(&format!("couldn't write css file: {}", &css_file));
+            .expect(&format!("couldn't write css file: {}", css_file.display()));
+    }
+
+    fn compile_syntax_css(&self) {
+        let css = posts::syntax_css().expect("couldn't generate syntax css");
+        fs::write(self.css_dir().join("syntax.css"), css).expect("couldn't write syntax.css");
     }
 
     fn concat_vendor_css(&self, files: Vec<&str>) {
         let mut concatted = String::new();
         for filestem in files {
-            let vendor_path = format!("./static/styles/{}.css", filestem);
+            let vendor_path = self.css_dir().join(format!("{}.css", filestem));
             let contents = fs::read_to_string(vendor_path).expect("couldn't read vendor css");
             concatted.push_str(&contents);
         }
-        fs::write("./static/styles/vendor.css", &concatted).expect("couldn't write vendor css");
+        fs::write(self.css_dir().join("vendor.css"), &concatted)
+            .expect("couldn't write vendor css");
     }
 
     fn render_blog(&self, blog: &Blog) -> Result<(), Box<dyn Error>> {
@@ -120,6 +162,12 @@ impl<'a> Generator<'a> {
 
         println!("{}: {}", blog.title(), self.file_url(&path));
 
+        let feed_path = self.render_feed(blog)?;
+        println!("├─ Feed: {}", self.file_url(&feed_path));
+
+        self.render_tags(blog)?;
+        self.render_archive(blog)?;
+
         for (i, post) in blog.posts().iter().enumerate() {
             let path = self.render_post(blog, post)?;
             if i == 0 {
@@ -131,15 +179,102 @@ impl<'a> Generator<'a> {
     }
 
     fn render_index(&self, blog: &Blog) -> Result<PathBuf, Box<dyn Error>> {
+        let per_page = blog.posts_per_page();
+        let chunks: Vec<&[Post]> = blog.posts().chunks(per_page).collect();
+        let total_pages = chunks.len().max(1);
+        let mut first_path = PathBuf::from("index.html");
+
+        for (i, posts) in chunks.iter().enumerate() {
+            let current_page = i + 1;
+            // The first page lives at the root; later pages at `page/N/`.
+            let (path, root) = if i == 0 {
+                (PathBuf::from("index.html"), blog.path_back_to_root())
+            } else {
+                (
+                    PathBuf::from("page").join(current_page.to_string()).join("index.html"),
+                    PathBuf::from("../../"),
+                )
+            };
+            if i == 0 {
+                first_path = path.clone();
+            } else {
+                fs::create_dir_all(self.out_directory.join(path.parent().unwrap()))?;
+            }
+
+            let data = json!({
+                "title": blog.index_title(),
+                "parent": "layout",
+                "blog": blog,
+                "posts": posts,
+                "current_page": current_page,
+                "total_pages": total_pages,
+                "prev": if i > 0 { Some(current_page - 1) } else { None },
+                "next": if current_page < total_pages { Some(current_page + 1) } else { None },
+                "root": root,
+            });
+            self.render_template(&path, "index", data)?;
+        }
+
+        Ok(first_path)
+    }
+
+    fn render_archive(&self, blog: &Blog) -> Result<(), Box<dyn Error>> {
+        let years = blog.years();
+        for (i, year) in years.iter().enumerate() {
+            let posts: Vec<&Post> = blog.posts().iter().filter(|p| p.year == *year).collect();
+            let dir = PathBuf::from("archive").join(year.to_string());
+            fs::create_dir_all(self.out_directory.join(&dir))?;
+
+            let data = json!({
+                "title": format!("{} | {}", year, blog.title()),
+                "parent": "layout",
+                "blog": blog,
+                "year": year,
+                "posts": posts,
+                // `years` is newest-first, so the next (newer) year precedes us.
+                "newer": if i > 0 { Some(years[i - 1]) } else { None },
+                "older": years.get(i + 1).copied(),
+                "root": PathBuf::from("../../"),
+            });
+            self.render_template(dir.join("index.html"), "archive", data)?;
+        }
+        Ok(())
+    }
+
+    fn render_feed(&self, blog: &Blog) -> Result<PathBuf, Box<dyn Error>> {
+        let atom = feed::render_atom(blog);
+        let path = PathBuf::from("feed.xml");
+        fs::write(self.out_directory.join(&path), atom)?;
+        Ok(path)
+    }
+
+    fn render_tags(&self, blog: &Blog) -> Result<(), Box<dyn Error>> {
+        // The overview page listing every tag with its post count.
+        fs::create_dir_all(self.out_directory.join("tags"))?;
         let data = json!({
-            "title": blog.index_title(),
+            "title": format!("Tags | {}", blog.title()),
             "parent": "layout",
             "blog": blog,
-            "root": blog.path_back_to_root(),
+            "tags": blog.tags(),
+            "root": PathBuf::from("../"),
         });
-        let path = PathBuf::from("index.html");
-        self.render_template(&path, "index", data)?;
-        Ok(path)
+        self.render_template(PathBuf::from("tags").join("index.html"), "tags", data)?;
+
+        // One listing page per tag.
+        for tag in blog.tags() {
+            let dir = PathBuf::from("tags").join(tag.slug());
+            fs::create_dir_all(self.out_directory.join(&dir))?;
+            let data = json!({
+                "title": format!("{} | {}", tag.slug(), blog.title()),
+                "parent": "layout",
+                "blog": blog,
+                "tag": tag,
+                "root": PathBuf::from("../../"),
+            });
+            self.render_template(dir.join("index.html"), "tag", data)?;
+        }
+
+        Ok(())
     }
 
     fn render_post(&self, blog: &Blog, post: &Post) -> Result<PathBuf, Box<dyn Error>> {
@@ -149,6 +284,11 @@ impl<'a> Generator<'a> {
             .join(format!("{:02}", &post.day));
         fs::create_dir_all(self.out_directory.join(&path))?;
 
+        // Copy any co-located assets from a folder-based post next to its page.
+        if let Some(asset_dir) = post.asset_dir() {
+            self.copy_post_assets(asset_dir, &self.out_directory.join(&path))?;
+        }
+
         // then, we render the page in that path
         let mut filename = PathBuf::from(&post.filename);
         filename.set_extension("html");
@@ -166,6 +306,30 @@ impl<'a> Generator<'a> {
         Ok(path)
     }
 
+    fn copy_post_assets(&self, from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+        use fs_extra::dir::{self, CopyOptions};
+
+        let mut options = CopyOptions::new();
+        options.overwrite = true;
+        options.copy_inside = true;
+
+        for entry in fs::read_dir(from)? {
+            let path = entry?.path();
+            // The post body itself is rendered, not copied verbatim.
+            if path.file_name().and_then(|n| n.to_str()) == Some("index.md") {
+                continue;
+            }
+            if path.is_dir() {
+                dir::copy(&path, to, &options)?;
+            } else {
+                let dest = to.join(path.file_name().unwrap());
+                fs::copy(&path, dest)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn copy_static_files(&self) -> Result<(), Box<dyn Error>> {
         use fs_extra::dir::{self, CopyOptions};
 
@@ -173,10 +337,11 @@ impl<'a> Generator<'a> {
         options.overwrite = true;
         options.copy_inside = true;
 
-        dir::copy("static/fonts", &self.out_directory, &options)?;
-        dir::copy("static/images", &self.out_directory, &options)?;
-        dir::copy("static/styles", &self.out_directory, &options)?;
-        dir::copy("static/scripts", &self.out_directory, &options)?;
+        let static_dir = &self.locations.static_dir;
+        dir::copy(static_dir.join("fonts"), &self.out_directory, &options)?;
+        dir::copy(static_dir.join("images"), &self.out_directory, &options)?;
+        dir::copy(static_dir.join("styles"), &self.out_directory, &options)?;
+        dir::copy(static_dir.join("scripts"), &self.out_directory, &options)?;
 
         Ok(())
     }
@@ -195,7 +360,23 @@ impl<'a> Generator<'a> {
 }
 
 pub fn main() -> Result<(), Box<dyn Error>> {
-    let blog = Generator::new("site", "posts")?;
+    // Defaults, then environment overrides, then CLI flags (highest priority).
+    let mut locations = Locations::default().with_env_overrides();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().expect("expected a value after flag");
+        match flag.as_str() {
+            "--out" => locations.out_dir = value().into(),
+            "--posts" => locations.posts_dir = value().into(),
+            "--templates" => locations.templates_dir = value().into(),
+            "--static" => locations.static_dir = value().into(),
+            "--styles" => locations.styles_dir = value().into(),
+            other => return Err(format!("unknown flag: {}", other).into()),
+        }
+    }
+
+    let blog = Generator::new(locations)?;
 
     blog.render()?;
 
@@ -1,12 +1,34 @@
-use comrak::{ComrakExtensionOptions, ComrakOptions, ComrakRenderOptions};
+use chrono::Datelike;
+use comrak::nodes::{AstNode, NodeHtmlBlock, NodeValue};
+use comrak::{parse_document, Arena, ComrakExtensionOptions, ComrakOptions, ComrakRenderOptions};
+use once_cell::sync::Lazy;
 use serde_derive::{Deserialize, Serialize};
 use std::error::Error;
 use std::path::{Path, PathBuf};
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+/// The syntect theme whose colors back the generated `syntax.css`.
+static THEME_NAME: &str = "InspiredGitHub";
+
+/// Syntax and theme definitions are expensive to load, so we build them once
+/// and share them across every post.
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEMES: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
 
 #[derive(Debug, PartialEq, Deserialize)]
 struct YamlHeader {
     title: String,
     author: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Overrides the filename-derived date when present; accepts `%Y-%m-%d` or
+    /// a full RFC3339 timestamp.
+    date: Option<String>,
+    /// An explicit "last updated" timestamp; defaults to the publish date.
+    updated: Option<String>,
+    #[serde(default)]
+    draft: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -17,8 +39,17 @@ pub(crate) struct Post {
     pub(crate) year: i32,
     pub(crate) show_year: bool,
     pub(crate) month: u32,
+    /// Directory a folder-based post was loaded from, whose sibling files are
+    /// co-located assets copied alongside the rendered page.
+    #[serde(skip)]
+    pub(crate) asset_dir: Option<PathBuf>,
     pub(crate) day: u32,
+    pub(crate) draft: bool,
+    pub(crate) tags: Vec<String>,
     pub(crate) contents: String,
+    pub(crate) excerpt: String,
+    pub(crate) word_count: usize,
+    pub(crate) reading_time: u64,
     pub(crate) url: String,
     pub(crate) published: String,
     pub(crate) updated: String,
@@ -26,11 +57,18 @@ pub(crate) struct Post {
 
 impl Post {
     pub(crate) fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
-        // yeah this might blow up, but it won't
-        let filename = path.file_name().unwrap().to_str().unwrap();
+        // A post is either a single `.md` file or a directory with an
+        // `index.md` whose sibling files are co-located assets.
+        let (entry_name, source, asset_dir) = if path.is_dir() {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            (name, path.join("index.md"), Some(path.to_path_buf()))
+        } else {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            (name, path.to_path_buf(), None)
+        };
 
-        // we need to get the metadata out of the url
-        let mut split = filename.splitn(4, "-");
+        // we need to get the metadata out of the name
+        let mut split = entry_name.splitn(4, "-");
 
         // we do some unwraps because these need to be valid
         let year = split.next().unwrap().parse::<i32>().unwrap();
@@ -38,7 +76,7 @@ impl Post {
         let day = split.next().unwrap().parse::<u32>().unwrap();
         let filename = split.next().unwrap().to_string();
 
-        let contents = std::fs::read_to_string(path)?;
+        let contents = std::fs::read_to_string(&source)?;
 
         // yaml headers.... we know the first four bytes of each file are "---\n"
         // so we need to find the end. we need the fours to adjust for those first bytes
@@ -47,6 +85,10 @@ impl Post {
         let YamlHeader {
             author,
             title,
+            tags,
+            date,
+            updated: updated_header,
+            draft,
         } = serde_yaml::from_str(yaml)?;
         // next, the contents. we add + to get rid of the final "---\n\n"
         let options = ComrakOptions {
@@ -63,8 +105,23 @@ impl Post {
             ..ComrakOptions::default()
         };
 
-        // Content starts after "---\n" (we don't assume an extra newline)
-        let contents = comrak::markdown_to_html(&contents[end_of_yaml + 4..], &options);
+        // Content starts after "---\n" (we don't assume an extra newline).
+        let body = &contents[end_of_yaml + 4..];
+        let contents = render_markdown(body, &options)?;
+
+        // An explicit `<!-- more -->` marker splits the post into a summary and
+        // the rest; without one we fall back to the first rendered paragraph.
+        let excerpt = match body.find("<!-- more -->") {
+            Some(marker) => render_markdown(&body[..marker], &options)?,
+            None => first_paragraph(&contents),
+        };
+
+        // Reading time is computed from the plaintext with HTML tags stripped,
+        // so markup (including the highlighter's spans) doesn't inflate the
+        // count; the text inside code blocks is still counted. Nominal ~200
+        // words per minute.
+        let word_count = strip_html(&contents).split_whitespace().count();
+        let reading_time = ((word_count as f64 / 200.0).ceil() as u64).max(1);
 
         // finally, the url.
         let mut url = PathBuf::from(&*filename);
@@ -79,8 +136,18 @@ impl Post {
             url.to_str().unwrap()
         );
 
-        let published = build_post_time(year, month, day, 0);
-        let updated = published.clone();
+        // A front-matter `date` overrides the filename-derived `published` time
+        // (and `updated`, unless that is given too), letting authors
+        // disambiguate same-day posts with a real clock time. The year, month,
+        // day and URL still come from the filename.
+        let published = match &date {
+            Some(raw) => parse_post_time(raw)?,
+            None => build_post_time(year, month, day, 0),
+        };
+        let updated = match &updated_header {
+            Some(raw) => parse_post_time(raw)?,
+            None => published.clone(),
+        };
 
         Ok(Self {
             filename,
@@ -90,16 +157,131 @@ impl Post {
             show_year: false,
             month,
             day,
+            asset_dir,
+            draft,
+            tags,
             contents,
+            excerpt,
+            word_count,
+            reading_time,
             url,
             published,
             updated,
         })
     }
 
-    pub fn set_updated(&mut self, seconds: u32) {
-        self.updated = build_post_time(self.year, self.month, self.day, seconds);
+
+    /// The directory a folder-based post was loaded from, if any.
+    pub(crate) fn asset_dir(&self) -> Option<&Path> {
+        self.asset_dir.as_deref()
+    }
+}
+
+/// Parse a front-matter timestamp, accepting a full RFC3339 value or a bare
+/// `%Y-%m-%d` date (treated as midnight UTC). Returns a normalized RFC3339
+/// string so it lines up with the filename-derived times.
+fn parse_post_time(raw: &str) -> Result<String, Box<dyn Error>> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Ok(dt.with_timezone(&chrono::Utc).to_rfc3339());
+    }
+    let date = chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")?;
+    Ok(build_post_time(date.year(), date.month(), date.day(), 0))
+}
+
+/// Render a markdown fragment to HTML, colorizing fenced code blocks on the
+/// way through comrak's AST.
+fn render_markdown(md: &str, options: &ComrakOptions) -> Result<String, Box<dyn Error>> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, md, options);
+    highlight_code_blocks(root);
+    let mut html = Vec::new();
+    comrak::format_html(root, options, &mut html)?;
+    Ok(String::from_utf8(html)?)
+}
+
+/// The first `<p>…</p>` block of some rendered HTML, or the whole input if it
+/// has no paragraph.
+fn first_paragraph(html: &str) -> String {
+    match (html.find("<p>"), html.find("</p>")) {
+        (Some(start), Some(end)) if end > start => html[start..end + "</p>".len()].to_string(),
+        _ => html.to_string(),
+    }
+}
+
+/// Strip HTML tags so word counting operates on visible text only.
+fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
     }
+    out
+}
+
+/// Replace every code block in the tree, fenced or indented, with a highlighted
+/// HTML block.
+fn highlight_code_blocks<'a>(root: &'a AstNode<'a>) {
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        let (code, lang) = match data.value {
+            NodeValue::CodeBlock(ref block) => {
+                let lang = block.info.split_whitespace().next().unwrap_or("").to_string();
+                (block.literal.clone(), lang)
+            }
+            _ => continue,
+        };
+        data.value = NodeValue::HtmlBlock(NodeHtmlBlock {
+            literal: highlight(&code, &lang),
+            block_type: 0,
+        });
+    }
+}
+
+/// Render `code` as a classed `<pre><code>` block. An unknown or missing
+/// language falls back to plain-text rendering rather than panicking.
+fn highlight(code: &str, lang: &str) -> String {
+    use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+    use syntect::util::LinesWithEndings;
+
+    let syntax = SYNTAXES
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, ClassStyle::Spaced);
+    for line in LinesWithEndings::from(code) {
+        if generator
+            .parse_html_for_line_which_includes_newline(line)
+            .is_err()
+        {
+            break;
+        }
+    }
+    let highlighted = generator.finalize();
+
+    let class = if lang.is_empty() {
+        String::from("code")
+    } else {
+        format!("code language-{}", lang)
+    };
+    format!(
+        "<pre class=\"code\"><code class=\"{}\">{}</code></pre>\n",
+        class, highlighted
+    )
+}
+
+/// The CSS for the chosen syntect theme, using the same spaced class style as
+/// the highlighter so the two agree on class names.
+pub(crate) fn syntax_css() -> Result<String, Box<dyn Error>> {
+    use syntect::html::{css_for_theme_with_class_style, ClassStyle};
+
+    let theme = &THEMES.themes[THEME_NAME];
+    Ok(css_for_theme_with_class_style(theme, ClassStyle::Spaced)?)
 }
 
 fn build_post_time(year: i32, month: u32, day: u32, seconds: u32) -> String {